@@ -0,0 +1,314 @@
+//! Burrows-Wheeler transform and the FM-index built on top of it.
+
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use fillings::ReprUsize;
+
+use io_util::{invalid_data, read_u64, write_u64, MAX_PREALLOC};
+use sa::suffix_array;
+use wavelet::WaveletTree;
+
+// Bumped whenever the on-disk layout written by `FMIndex::to_writer` changes.
+const FORMAT_VERSION: u8 = 1;
+
+/// Computes the Burrows-Wheeler transform of `text`, returning the transformed
+/// sequence and the index of the original sequence's row in the (conceptual)
+/// matrix of sorted rotations.
+///
+/// `text` must not contain the symbol whose code is `0`; that code is reserved
+/// as the unique sentinel appended internally to make the rotations sortable.
+pub fn bwt<T: ReprUsize + Clone>(text: &[T]) -> (Vec<T>, usize) {
+    let mut codes: Vec<usize> = text.iter().cloned().map(ReprUsize::into_usize).collect();
+    codes.push(0);
+    let sa = suffix_array(&codes);
+
+    let mut primary = 0;
+    let transformed = sa.iter().enumerate().map(|(row, &suffix)| {
+        let code = if suffix == 0 {
+            primary = row;
+            codes[codes.len() - 1]
+        } else {
+            codes[suffix - 1]
+        };
+        T::from_usize(code)
+    }).collect();
+
+    (transformed, primary)
+}
+
+/// Inverts [`bwt`], recovering the original sequence from the transformed one
+/// and the primary index it returned.
+pub fn ibwt<T: ReprUsize + Clone>(transformed: &[T], primary: usize) -> Vec<T> {
+    let codes: Vec<usize> = transformed.iter().cloned().map(ReprUsize::into_usize).collect();
+    let n = codes.len();
+
+    // Stably sorting (code, original index) pairs gives, at each sorted
+    // position, the row of the BWT matrix that sorted row came from; its
+    // inverse permutation is the LF-mapping used to walk the text backwards.
+    let mut pairs: Vec<(usize, usize)> = codes.iter().cloned().zip(0..n).collect();
+    pairs.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let mut lf = vec![0; n];
+    for (sorted_row, &(_, original_row)) in pairs.iter().enumerate() {
+        lf[original_row] = sorted_row;
+    }
+
+    let mut decoded = vec![0; n];
+    let mut row = primary;
+    for slot in decoded.iter_mut().rev() {
+        *slot = codes[row];
+        row = lf[row];
+    }
+
+    decoded.pop(); // drop the sentinel appended by `bwt`
+    decoded.into_iter().map(T::from_usize).collect()
+}
+
+/// An FM-index: a compressed full-text index supporting `count`-style queries
+/// without ever materializing the original text, backed by a [`WaveletTree`]
+/// over the Burrows-Wheeler transform so it works for any `ReprUsize` alphabet.
+pub struct FMIndex<T: ReprUsize> {
+    bwt: WaveletTree,
+    // Cumulative count of symbols smaller than each code, i.e. the classic FM-index `C` array.
+    c: Vec<usize>,
+    primary: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ReprUsize + Clone> FMIndex<T> {
+    pub fn new(text: &[T]) -> FMIndex<T> {
+        let (transformed, primary) = bwt(text);
+        let codes: Vec<usize> = transformed.iter().cloned().map(ReprUsize::into_usize).collect();
+        let alphabet_size = codes.iter().cloned().max().map_or(0, |max| max + 1);
+
+        let mut c = vec![0; alphabet_size + 1];
+        for &code in &codes {
+            c[code + 1] += 1;
+        }
+        for i in 1..c.len() {
+            c[i] += c[i - 1];
+        }
+
+        let len = codes.len();
+        FMIndex {
+            bwt: WaveletTree::new(&codes, alphabet_size),
+            c: c,
+            primary: primary,
+            len: len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Counts the occurrences of `pattern` in the original text via backward
+    /// search. O(`pattern.len()` * log sigma).
+    pub fn count(&mut self, pattern: &[T]) -> usize {
+        let mut lo = 0;
+        let mut hi = self.len;
+
+        for symbol in pattern.iter().rev() {
+            let code = symbol.clone().into_usize();
+            if code + 1 >= self.c.len() {
+                return 0
+            }
+
+            lo = self.c[code] + self.bwt.rank(lo, code);
+            hi = self.c[code] + self.bwt.rank(hi, code);
+            if lo >= hi {
+                return 0
+            }
+        }
+
+        hi - lo
+    }
+
+    /// Reconstructs the original text from the index by repeatedly walking
+    /// the LF-mapping backwards from the primary row. O(n log sigma).
+    pub fn to_vec(&mut self) -> Vec<T> {
+        let mut row = self.primary;
+        let mut out = vec![T::from_usize(0); self.len];
+
+        for slot in out.iter_mut().rev() {
+            let code = self.bwt.access(row);
+            *slot = T::from_usize(code);
+            row = self.c[code] + self.bwt.rank(row, code);
+        }
+
+        out.pop(); // drop the sentinel that `bwt` appended before indexing
+        out
+    }
+
+    /// Finds the BWT row holding the `occurrence`-th (0-indexed) instance of
+    /// `symbol`, or `None` if `symbol` occurs fewer times than that. This is
+    /// the `select` counterpart of the `rank` queries `count`/`to_vec` use.
+    pub fn row_of(&mut self, symbol: T, occurrence: usize) -> Option<usize> {
+        self.bwt.select(symbol.into_usize(), occurrence)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn primary_index(&self) -> usize {
+        self.primary
+    }
+
+    /// Writes this index to `writer`: a header of the format version, `c`,
+    /// `primary` and `len`, followed by the underlying wavelet tree.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_u64(writer, FORMAT_VERSION as u64)?;
+        write_u64(writer, self.c.len() as u64)?;
+        for &count in &self.c {
+            write_u64(writer, count as u64)?;
+        }
+        write_u64(writer, self.primary as u64)?;
+        write_u64(writer, self.len as u64)?;
+        self.bwt.to_writer(writer)
+    }
+
+    /// Reconstructs an `FMIndex` previously written with `to_writer`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<FMIndex<T>> {
+        let version = read_u64(reader)?;
+        if version != FORMAT_VERSION as u64 {
+            return Err(invalid_data(format!("unsupported FMIndex format version {}", version)))
+        }
+
+        let c_len = read_u64(reader)? as usize;
+        let mut c = Vec::with_capacity(cmp::min(c_len, MAX_PREALLOC));
+        for _ in 0..c_len {
+            c.push(read_u64(reader)? as usize);
+        }
+
+        let primary = read_u64(reader)? as usize;
+        let len = read_u64(reader)? as usize;
+        let bwt = WaveletTree::from_reader(reader)?;
+
+        Ok(FMIndex { bwt: bwt, c: c, primary: primary, len: len, _marker: PhantomData })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{bwt, ibwt, FMIndex};
+
+    fn codes(s: &str) -> Vec<usize> {
+        // Shift every byte up by one so that code `0` stays free for the sentinel.
+        s.bytes().map(|b| b as usize + 1).collect()
+    }
+
+    #[test]
+    fn test_bwt_roundtrip() {
+        for text in &["banana", "abracadabra", "mississippi", "a", "helixrustwavelet"] {
+            let input = codes(text);
+            let (transformed, primary) = bwt(&input);
+            assert_eq!(ibwt(&transformed, primary), input);
+        }
+    }
+
+    #[test]
+    fn test_bwt_roundtrip_empty() {
+        let input: Vec<usize> = vec![];
+        let (transformed, primary) = bwt(&input);
+        assert_eq!(ibwt(&transformed, primary), input);
+    }
+
+    fn naive_count(text: &str, pattern: &str) -> usize {
+        if pattern.is_empty() {
+            return 0
+        }
+        (0..=text.len().saturating_sub(pattern.len()))
+            .filter(|&i| &text[i..i + pattern.len()] == pattern)
+            .count()
+    }
+
+    #[test]
+    fn test_fm_index_count_matches_naive() {
+        let text = "mississippi";
+        let mut index = FMIndex::new(&codes(text));
+
+        for pattern in &["i", "s", "ss", "issi", "ip", "miss", "z", "mississippi"] {
+            assert_eq!(index.count(&codes(pattern)), naive_count(text, pattern), "pattern {:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_fm_index_to_vec_roundtrips() {
+        for text in &["banana", "abracadabra", "mississippi", "a"] {
+            let mut index = FMIndex::new(&codes(text));
+            assert_eq!(index.to_vec(), codes(text));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_over_unicode_chars() {
+        // `char` codes beyond Latin-1 (CJK, emoji) so `FMIndex<char>` earns
+        // its "large alphabets" billing instead of only ever seeing bytes.
+        let text: Vec<char> = "héllo 世界 🎉🎉".chars().collect();
+        let mut index = FMIndex::new(&text);
+
+        assert_eq!(index.to_vec(), text);
+        assert_eq!(index.count(&['世', '界']), 1);
+        assert_eq!(index.count(&['🎉']), 2);
+        assert_eq!(index.count(&['x']), 0);
+    }
+
+    #[test]
+    fn test_fm_index_row_of_is_consistent_with_access() {
+        let text = "mississippi";
+        let mut index = FMIndex::new(&codes(text));
+
+        let s_code = (b's' as usize) + 1;
+        for occurrence in 0..4 {
+            let row = index.row_of(s_code, occurrence).expect("s occurs 4 times");
+            assert_eq!(index.bwt.access(row), s_code);
+        }
+        assert_eq!(index.row_of(s_code, 4), None);
+    }
+
+    #[test]
+    fn test_fm_index_io_roundtrip() {
+        for text in &["banana", "abracadabra", "mississippi", "a"] {
+            let mut index = FMIndex::new(&codes(text));
+
+            let mut buf = Vec::new();
+            index.to_writer(&mut buf).unwrap();
+
+            let mut restored: FMIndex<usize> = FMIndex::from_reader(&mut &buf[..]).unwrap();
+            assert_eq!(restored.to_vec(), codes(text));
+            assert_eq!(restored.count(&codes("i")), index.count(&codes("i")));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_io_roundtrip_empty() {
+        let index: FMIndex<usize> = FMIndex::new(&[]);
+
+        let mut buf = Vec::new();
+        index.to_writer(&mut buf).unwrap();
+
+        let mut restored: FMIndex<usize> = FMIndex::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(restored.to_vec(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_fm_index_from_reader_rejects_corrupted_huge_length_gracefully() {
+        use io_util::write_u64;
+
+        let mut buf = Vec::new();
+        write_u64(&mut buf, super::FORMAT_VERSION as u64).unwrap();
+        write_u64(&mut buf, u64::MAX).unwrap(); // c_len: corrupted/huge
+        // No further data follows, so a correct reader must fail on the first
+        // read rather than trying to allocate for `u64::MAX` counts.
+
+        let result: io::Result<FMIndex<usize>> = FMIndex::from_reader(&mut &buf[..]);
+        assert!(result.is_err());
+    }
+}
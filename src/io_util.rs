@@ -0,0 +1,26 @@
+//! Little-endian `u64` framing helpers shared by the on-disk formats of
+//! `FMIndex`, `Trie` and `WaveletTree` (suffix arrays go through
+//! `BitsVec::to_writer` instead).
+
+use std::io::{self, Read, Write};
+
+/// Cap on how much a length field read from an untrusted header is allowed to
+/// preallocate up front. A corrupt or hostile length still gets read in full
+/// (the `Vec`/`HashMap` just grows as it goes), but it can no longer force an
+/// instant multi-gigabyte allocation before a single element has actually
+/// been read.
+pub(crate) const MAX_PREALLOC: usize = 1 << 20;
+
+pub(crate) fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -2,10 +2,12 @@ extern crate fillings;
 extern crate num_traits;
 
 mod bwt;
+mod io_util;
 mod sa;
 mod trie;
+mod wavelet;
 
 pub use bwt::{bwt, ibwt, FMIndex};
 pub use fillings::BitsVec;
-pub use sa::suffix_array;
+pub use sa::{read_suffix_array, suffix_array, suffix_array_sais, write_suffix_array};
 pub use trie::Trie;
@@ -1,10 +1,22 @@
 use std::cmp;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Range;
+use std::slice;
 use std::usize;
 
+// Bumped whenever the on-disk layout written by `to_writer` changes.
+const FORMAT_VERSION: u8 = 1;
+
+// Cap on how much a length field read from an untrusted header is allowed to
+// preallocate up front. A corrupt or hostile length still gets read in full
+// (the `Vec` just grows as it goes), but it can no longer force an instant
+// multi-gigabyte allocation before a single element has actually been read.
+const MAX_PREALLOC: usize = 1 << 20;
+
 pub trait ReprUsize {
     fn from_usize(usize) -> Self;
     fn into_usize(self) -> usize;
@@ -23,8 +35,14 @@ impl ReprUsize for bool {
 }
 
 impl ReprUsize for char {
-    fn from_usize(i: usize) -> char { i as u8 as char }
-    fn into_usize(self) -> usize { self as u8 as usize }
+    // `char` covers the full Unicode scalar value range (up to `U+10FFFF`,
+    // minus the surrogate gap), not just Latin-1, so round-tripping through
+    // `u8` would silently truncate most real text. Go through `u32` instead.
+    fn from_usize(i: usize) -> char {
+        char::from_u32(i as u32).expect("[from_usize] not a valid Unicode scalar value")
+    }
+
+    fn into_usize(self) -> usize { self as u32 as usize }
 }
 
 macro_rules! impl_predefined_type {
@@ -49,16 +67,52 @@ impl_predefined_type!(isize);
 impl_predefined_type!(f32);
 impl_predefined_type!(f64);
 
-#[derive(Clone, Hash)]
 pub struct BitsVec<T: ReprUsize> {
     inner: Vec<usize>,
     units: usize,
     bits: usize,
     max_bits: usize,
     leftover: usize,
+    // Two-level rank/select directory over `inner`, built lazily by `rank1`/`select1`
+    // and torn down again by any mutation, or rebuilt once `inner` has grown past
+    // it (only ever populated for `BitsVec<bool>`).
+    directory: Option<RankDirectory>,
     _marker: PhantomData<T>,
 }
 
+impl<T: ReprUsize> Clone for BitsVec<T> {
+    fn clone(&self) -> Self {
+        BitsVec {
+            inner: self.inner.clone(),
+            units: self.units,
+            bits: self.bits,
+            max_bits: self.max_bits,
+            leftover: self.leftover,
+            directory: self.directory.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ReprUsize> Hash for BitsVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+        self.units.hash(state);
+        self.bits.hash(state);
+    }
+}
+
+// Two-level Jacobson rank directory over `inner`: `superblocks[b]` is the
+// cumulative popcount before superblock `b`, and `word_ranks[w]` is the
+// popcount of the words before word `w` *within its own superblock*. Together
+// they let `rank1` jump straight to the right word without rescanning.
+#[derive(Clone)]
+struct RankDirectory {
+    superblocks: Vec<usize>,
+    word_ranks: Vec<usize>,
+    total: usize,
+}
+
 impl<T: ReprUsize> BitsVec<T> {
     pub fn new(bits: usize) -> BitsVec<T> {
         let max = usize::MAX.count_ones() as usize;
@@ -71,6 +125,7 @@ impl<T: ReprUsize> BitsVec<T> {
             bits: bits,
             max_bits: max,
             leftover: max,
+            directory: None,
             _marker: PhantomData,
         }
     }
@@ -85,6 +140,7 @@ impl<T: ReprUsize> BitsVec<T> {
         let mut value = value.into_usize();
         assert!(value >> self.bits == 0,
                 "[push] input size is more than allowed size ({} >= {})", value, 2usize.pow(self.bits as u32));
+        self.directory = None;
 
         let mut idx = self.inner.len() - 1;
         let shift;
@@ -120,21 +176,7 @@ impl<T: ReprUsize> BitsVec<T> {
             return None
         }
 
-        let idx = i * self.bits / self.max_bits;
-        let bits = (i * self.bits) % self.max_bits;
-        let diff = self.max_bits - bits;
-        let mut val = self.inner[idx];
-        if bits != 0 {
-            val &= (1 << diff) - 1;
-        }
-
-        if diff >= self.bits {
-            Some(T::from_usize(val >> (diff - self.bits)))
-        } else {
-            let shift = self.bits - diff;
-            let out = (val << shift) | (self.inner[idx + 1] >> (self.max_bits - shift));
-            Some(T::from_usize(out))
-        }
+        Some(extract_bits(&self.inner, self.bits, self.max_bits, i))
     }
 
     pub fn set(&mut self, i: usize, value: T) {
@@ -142,6 +184,7 @@ impl<T: ReprUsize> BitsVec<T> {
         assert!(i < self.units, "[set] index out of bounds ({} >= {})", i, self.units);
         assert!(value >> self.bits == 0,
                 "[set] input size is more than allowed size ({} >= {})", value, 2usize.pow(self.bits as u32));
+        self.directory = None;
 
         let idx = i * self.bits / self.max_bits;
         let bits = (i * self.bits) % self.max_bits;
@@ -183,6 +226,88 @@ impl<T: ReprUsize> BitsVec<T> {
         mem::replace(self, BitsVec::new(bits));
     }
 
+    /// Removes and returns the last element, or `None` if empty. Unlike
+    /// `remove`, this never shifts any other element: it just zeroes the freed
+    /// bits, shrinks `units`, recomputes `leftover`, and drops the trailing
+    /// word once it's unused.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.units == 0 {
+            return None
+        }
+
+        let last = self.units - 1;
+        let value = self.get(last);
+        self.set(last, T::from_usize(0));
+        self.units = last;
+
+        let total_bits = self.units * self.bits;
+        let required_len = cmp::max(1, (total_bits + self.max_bits - 1) / self.max_bits);
+        self.inner.truncate(required_len);
+        self.leftover = required_len * self.max_bits - total_bits;
+        self.directory = None;
+
+        Some(value)
+    }
+
+    /// Shortens the vector to `len` elements by repeatedly popping the tail.
+    pub fn truncate(&mut self, len: usize) {
+        while self.units > len {
+            self.pop();
+        }
+    }
+
+    /// Removes and returns the element at `i`, shifting every later element
+    /// down by one `bits`-wide unit: a single bulk shift of the `inner` word
+    /// stream rather than a `get`/`set` per shifted element.
+    pub fn remove(&mut self, i: usize) -> T {
+        assert!(i < self.units, "[remove] index out of bounds ({} >= {})", i, self.units);
+        let value = self.get(i);
+        self.directory = None;
+
+        let tail_bits = (self.units - i - 1) * self.bits;
+        if tail_bits > 0 {
+            shift_bits_down(&mut self.inner, self.max_bits, (i + 1) * self.bits, tail_bits, self.bits);
+        }
+
+        self.pop();
+        value
+    }
+
+    /// Inserts `value` at `i`, shifting `i..` up by one `bits`-wide unit: a
+    /// single bulk shift of the `inner` word stream rather than a `get`/`set`
+    /// per shifted element.
+    pub fn insert(&mut self, i: usize, value: T) {
+        assert!(i <= self.units, "[insert] index out of bounds ({} > {})", i, self.units);
+
+        if self.units == 0 {
+            self.push(value);
+            return
+        }
+
+        // Grow by duplicating the current last element; the shift below (or,
+        // when inserting at the end, the final `set`) overwrites that duplicate.
+        let last = self.get(self.units - 1);
+        self.push(last);
+        self.directory = None;
+
+        let tail_bits = (self.units - 1 - i) * self.bits;
+        if tail_bits > 0 {
+            shift_bits_up(&mut self.inner, self.max_bits, i * self.bits, tail_bits, self.bits);
+        }
+
+        self.set(i, value);
+    }
+
+    /// Removes `range` from the vector, returning an iterator over the
+    /// removed elements. Elements are shifted down (via repeated `remove`) as
+    /// the iterator advances; dropping it before exhausting it still removes
+    /// the rest of the range, just like `Vec::drain`.
+    pub fn drain(&mut self, range: Range<usize>) -> Drain<T> {
+        assert!(range.start <= range.end && range.end <= self.units,
+                "[drain] range out of bounds ({:?}), len is {}", range, self.units);
+        Drain { vec: self, start: range.start, end: range.end }
+    }
+
     pub fn inner_len(&self) -> usize {
         self.inner.len()
     }
@@ -194,6 +319,388 @@ impl<T: ReprUsize> BitsVec<T> {
     pub fn into_iter(self) -> IntoIter<T> {
         IntoIter { range: 0..self.units, vec: self }
     }
+
+    /// Writes this vector to `writer`: a header of the format version,
+    /// `bits`/`units`/`max_bits`/`leftover`/`inner.len()`, and then `inner`
+    /// itself, all as little-endian `u64`s.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_u64(writer, FORMAT_VERSION as u64)?;
+        write_u64(writer, self.bits as u64)?;
+        write_u64(writer, self.units as u64)?;
+        write_u64(writer, self.max_bits as u64)?;
+        write_u64(writer, self.leftover as u64)?;
+        write_u64(writer, self.inner.len() as u64)?;
+        for &word in &self.inner {
+            write_u64(writer, word as u64)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reconstructs a `BitsVec` previously written with `to_writer`, using
+    /// `Read::read_exact` instead of replaying every `push`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<BitsVec<T>> {
+        let version = read_u64(reader)?;
+        if version != FORMAT_VERSION as u64 {
+            return Err(invalid_data(format!("unsupported BitsVec format version {}", version)))
+        }
+
+        let bits = read_u64(reader)? as usize;
+        let units = read_u64(reader)? as usize;
+        let max_bits = read_u64(reader)? as usize;
+        let leftover = read_u64(reader)? as usize;
+        let inner_len = read_u64(reader)? as usize;
+
+        let mut inner = Vec::with_capacity(cmp::min(inner_len, MAX_PREALLOC));
+        for _ in 0..inner_len {
+            inner.push(read_u64(reader)? as usize);
+        }
+
+        Ok(BitsVec {
+            inner: inner,
+            units: units,
+            bits: bits,
+            max_bits: max_bits,
+            leftover: leftover,
+            directory: None,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Reconstructs a `BitsVec` from an in-memory buffer previously written by
+    /// `to_writer`, copying its word buffer into a freshly owned `Vec`. This
+    /// is just `from_reader` over a `&[u8]`; for loading multi-gigabyte
+    /// indexes without that copy, use [`BitsVecRef::from_bytes`] instead.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<BitsVec<T>> {
+        Self::from_reader(&mut &bytes[..])
+    }
+}
+
+/// Extracts the `bits`-wide element at index `i` out of a packed `inner`
+/// word stream, the arithmetic shared by `BitsVec::checked_get` and
+/// `BitsVecRef::checked_get`.
+fn extract_bits<T: ReprUsize>(inner: &[usize], bits: usize, max_bits: usize, i: usize) -> T {
+    let idx = i * bits / max_bits;
+    let bit_off = (i * bits) % max_bits;
+    let diff = max_bits - bit_off;
+    let mut val = inner[idx];
+    if bit_off != 0 {
+        val &= (1 << diff) - 1;
+    }
+
+    if diff >= bits {
+        T::from_usize(val >> (diff - bits))
+    } else {
+        let shift = bits - diff;
+        let out = (val << shift) | (inner[idx + 1] >> (max_bits - shift));
+        T::from_usize(out)
+    }
+}
+
+/// Reads the `len` bits (`len <= max_bits`) starting `off` bits into
+/// `inner[word]`, MSB-first, as the low `len` bits of the returned `usize`.
+fn read_bits(inner: &[usize], max_bits: usize, word: usize, off: usize, len: usize) -> usize {
+    if len == max_bits {
+        return inner[word]
+    }
+
+    let shift = max_bits - off - len;
+    (inner[word] >> shift) & ((1 << len) - 1)
+}
+
+/// Writes the low `len` bits of `value` (`len <= max_bits`) into `inner[word]`
+/// starting `off` bits in, MSB-first, leaving the surrounding bits untouched.
+fn write_bits(inner: &mut [usize], max_bits: usize, word: usize, off: usize, len: usize, value: usize) {
+    if len == max_bits {
+        inner[word] = value;
+        return
+    }
+
+    let shift = max_bits - off - len;
+    let mask = ((1 << len) - 1) << shift;
+    inner[word] = (inner[word] & !mask) | ((value << shift) & mask);
+}
+
+/// How many bits remain before the word boundary at or before bit `pos`,
+/// i.e. how large a chunk can end exactly at `pos` without crossing into the
+/// previous word.
+fn bits_before_boundary(pos: usize, max_bits: usize) -> usize {
+    let r = pos % max_bits;
+    if r == 0 { max_bits } else { r }
+}
+
+/// Moves the `len` bits starting at bit `src_start` down to `src_start -
+/// shift`, word-chunk by word-chunk rather than bit-by-bit or element-by-
+/// element. Processes ascending (the destination always trails the source),
+/// which is safe since overlapping regions never read data already overwritten.
+fn shift_bits_down(inner: &mut [usize], max_bits: usize, src_start: usize, len: usize, shift: usize) {
+    let mut src = src_start;
+    let mut dst = src_start - shift;
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let chunk = cmp::min(remaining, cmp::min(max_bits - src % max_bits, max_bits - dst % max_bits));
+        let bits = read_bits(inner, max_bits, src / max_bits, src % max_bits, chunk);
+        write_bits(inner, max_bits, dst / max_bits, dst % max_bits, chunk, bits);
+
+        src += chunk;
+        dst += chunk;
+        remaining -= chunk;
+    }
+}
+
+/// Moves the `len` bits starting at bit `src_start` up to `src_start +
+/// shift`, word-chunk by word-chunk. Processes descending (from the tail
+/// backward), which is safe since the destination always leads the source,
+/// so writes never clobber source data that hasn't been read yet.
+fn shift_bits_up(inner: &mut [usize], max_bits: usize, src_start: usize, len: usize, shift: usize) {
+    let mut src_end = src_start + len;
+    let mut dst_end = src_end + shift;
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let chunk = cmp::min(remaining, cmp::min(bits_before_boundary(src_end, max_bits), bits_before_boundary(dst_end, max_bits)));
+        src_end -= chunk;
+        dst_end -= chunk;
+
+        let bits = read_bits(inner, max_bits, src_end / max_bits, src_end % max_bits, chunk);
+        write_bits(inner, max_bits, dst_end / max_bits, dst_end % max_bits, chunk, bits);
+
+        remaining -= chunk;
+    }
+}
+
+/// A borrowed, zero-copy view over a `BitsVec`'s on-disk representation: the
+/// word buffer is reinterpreted directly out of `bytes` instead of being
+/// copied element-by-element into a freshly allocated `Vec`, so loading a
+/// multi-gigabyte index is just validating a header rather than scanning it.
+///
+/// `bytes` must stay alive and unchanged for as long as the `BitsVecRef`
+/// borrowing it is in use.
+pub struct BitsVecRef<'a, T: ReprUsize> {
+    inner: &'a [usize],
+    units: usize,
+    bits: usize,
+    max_bits: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: ReprUsize> Clone for BitsVecRef<'a, T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, T: ReprUsize> Copy for BitsVecRef<'a, T> {}
+
+impl<'a, T: ReprUsize> BitsVecRef<'a, T> {
+    /// Reconstructs a zero-copy view of a `BitsVec` previously written with
+    /// `to_writer`, reinterpreting its word buffer in place out of `bytes`.
+    /// Fails gracefully with an `io::Error` (rather than panicking or
+    /// triggering UB) if `bytes` is truncated, corrupt, or not aligned to `usize`.
+    pub fn from_bytes(bytes: &'a [u8]) -> io::Result<BitsVecRef<'a, T>> {
+        let mut header = bytes;
+        let version = read_u64(&mut header)?;
+        if version != FORMAT_VERSION as u64 {
+            return Err(invalid_data(format!("unsupported BitsVec format version {}", version)))
+        }
+
+        let bits = read_u64(&mut header)? as usize;
+        let units = read_u64(&mut header)? as usize;
+        let max_bits = read_u64(&mut header)? as usize;
+        let _leftover = read_u64(&mut header)?;
+        let inner_len = read_u64(&mut header)? as usize;
+
+        let word_size = mem::size_of::<usize>();
+        let byte_len = inner_len.checked_mul(word_size)
+            .ok_or_else(|| invalid_data("BitsVec inner length overflows a byte count"))?;
+
+        if header.len() < byte_len {
+            return Err(invalid_data("BitsVec byte buffer is shorter than its declared inner length"))
+        }
+        let word_bytes = &header[..byte_len];
+
+        if (word_bytes.as_ptr() as usize) % mem::align_of::<usize>() != 0 {
+            return Err(invalid_data("BitsVec byte buffer is not aligned for in-place usize access"))
+        }
+
+        // SOUND: `word_bytes` has been checked above to hold exactly
+        // `inner_len` `usize`s worth of bytes (`byte_len`, computed with an
+        // overflow check) and to be aligned to `usize`; every bit pattern is
+        // a valid `usize`, so reinterpreting it in place can't produce UB.
+        let inner = unsafe { slice::from_raw_parts(word_bytes.as_ptr() as *const usize, inner_len) };
+
+        Ok(BitsVecRef { inner: inner, units: units, bits: bits, max_bits: max_bits, _marker: PhantomData })
+    }
+
+    pub fn get(&self, i: usize) -> T {
+        assert!(i < self.units, "[get] index out of bounds ({} >= {})", i, self.units);
+        self.checked_get(i).unwrap()
+    }
+
+    pub fn checked_get(&self, i: usize) -> Option<T> {
+        if i >= self.units {
+            return None
+        }
+
+        Some(extract_bits(self.inner, self.bits, self.max_bits, i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.units
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units == 0
+    }
+
+    pub fn iter(&self) -> RefIter<'a, T> {
+        RefIter { vec: *self, range: 0..self.units }
+    }
+}
+
+pub struct RefIter<'a, T: ReprUsize> {
+    vec: BitsVecRef<'a, T>,
+    range: Range<usize>,
+}
+
+impl<'a, T: ReprUsize> IntoIterator for BitsVecRef<'a, T> {
+    type Item = T;
+    type IntoIter = RefIter<'a, T>;
+
+    fn into_iter(self) -> RefIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: ReprUsize> Iterator for RefIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.range.next().map(|i| self.vec.get(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, T: ReprUsize> DoubleEndedIterator for RefIter<'a, T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.range.next_back().map(|i| self.vec.get(i))
+    }
+}
+
+impl<'a, T: ReprUsize> ExactSizeIterator for RefIter<'a, T> {}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl BitsVec<bool> {
+    // Words per superblock in the rank directory (8 words == 512 bits on a 64-bit target).
+    const SUPERBLOCK_WORDS: usize = 8;
+
+    fn build_directory(&self) -> RankDirectory {
+        let mut superblocks = Vec::with_capacity(self.inner.len() / Self::SUPERBLOCK_WORDS + 1);
+        let mut word_ranks = Vec::with_capacity(self.inner.len());
+        let mut total = 0;
+        for chunk in self.inner.chunks(Self::SUPERBLOCK_WORDS) {
+            superblocks.push(total);
+            let mut within_block = 0;
+            for word in chunk {
+                word_ranks.push(within_block);
+                within_block += word.count_ones() as usize;
+            }
+            total += within_block;
+        }
+
+        RankDirectory { superblocks: superblocks, word_ranks: word_ranks, total: total }
+    }
+
+    fn directory(&mut self) -> &RankDirectory {
+        // `extend_with_element` appends whole words without going through
+        // `push`, so a directory covering fewer words than `inner` is stale too.
+        let inner_len = self.inner.len();
+        if self.directory.as_ref().map_or(true, |d| d.word_ranks.len() != inner_len) {
+            self.directory = Some(self.build_directory());
+        }
+
+        self.directory.as_ref().unwrap()
+    }
+
+    /// Counts the number of set bits in `[0, i)`. Builds the rank/select directory
+    /// on first use (or after the vector was last mutated), then answers in O(1):
+    /// the superblock and per-word side arrays locate the right word directly,
+    /// leaving only a popcount of the partial word in range.
+    pub fn rank1(&mut self, i: usize) -> usize {
+        assert!(i <= self.units, "[rank1] index out of bounds ({} > {})", i, self.units);
+        if i == 0 {
+            return 0
+        }
+
+        let max_bits = self.max_bits;
+        let word_idx = (i - 1) / max_bits;
+        let bit_off = (i - 1) % max_bits;
+        let block_idx = word_idx / Self::SUPERBLOCK_WORDS;
+
+        let directory = self.directory();
+        let mut count = directory.superblocks[block_idx] + directory.word_ranks[word_idx];
+
+        // The top `bit_off + 1` bits of `inner[word_idx]` are the ones in range.
+        let mask = !0usize << (max_bits - bit_off - 1);
+        count += (self.inner[word_idx] & mask).count_ones() as usize;
+        count
+    }
+
+    /// Finds the position of the `j`-th set bit (0-indexed), or `None` if the
+    /// vector has `j` or fewer set bits. O(log n) once the directory is built.
+    pub fn select1(&mut self, j: usize) -> Option<usize> {
+        let max_bits = self.max_bits;
+        if j >= self.directory().total {
+            return None
+        }
+
+        let superblocks = &self.directory.as_ref().unwrap().superblocks;
+        let mut lo = 0;
+        let mut hi = superblocks.len() - 1;
+        while lo < hi {
+            let mid = (lo + hi + 1) / 2;
+            if superblocks[mid] <= j {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        let mut count = superblocks[lo];
+        let mut word_idx = lo * Self::SUPERBLOCK_WORDS;
+        loop {
+            let ones = self.inner[word_idx].count_ones() as usize;
+            if count + ones > j {
+                break
+            }
+            count += ones;
+            word_idx += 1;
+        }
+
+        // Reverse so the bit at local offset 0 (the MSB) becomes the LSB, then
+        // repeatedly clear the lowest set bit until the target one is the only one left.
+        let mut word = self.inner[word_idx].reverse_bits();
+        for _ in 0..(j - count) {
+            word &= word - 1;
+        }
+
+        Some(word_idx * max_bits + word.trailing_zeros() as usize)
+    }
 }
 
 impl<T: ReprUsize + Clone> BitsVec<T> {
@@ -331,9 +838,55 @@ impl<T: ReprUsize> DoubleEndedIterator for IntoIter<T> {
 
 impl<T: ReprUsize> ExactSizeIterator for IntoIter<T> {}
 
+pub struct Drain<'a, T: ReprUsize + 'a> {
+    vec: &'a mut BitsVec<T>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a, T: ReprUsize> Iterator for Drain<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None
+        }
+
+        self.end -= 1;
+        Some(self.vec.remove(self.start))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.start;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: ReprUsize> DoubleEndedIterator for Drain<'a, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None
+        }
+
+        self.end -= 1;
+        Some(self.vec.remove(self.end))
+    }
+}
+
+impl<'a, T: ReprUsize> ExactSizeIterator for Drain<'a, T> {}
+
+impl<'a, T: ReprUsize> Drop for Drain<'a, T> {
+    fn drop(&mut self) {
+        while self.start < self.end {
+            self.vec.remove(self.start);
+            self.end -= 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{BitsVec, ReprUsize};
+    use super::{write_u64, BitsVec, ReprUsize};
     use std::mem;
 
     #[repr(usize)]
@@ -350,6 +903,25 @@ mod tests {
         fn from_usize(i: usize) -> Self { unsafe { mem::transmute(i) } }
     }
 
+    #[test]
+    fn test_char_repr_usize_round_trips_beyond_latin1() {
+        for &c in &['a', 'é', 'ह', '世', '\u{10FFFF}', '\u{1F389}'] {
+            assert_eq!(char::from_usize(c.into_usize()), c, "{:?} did not round-trip", c);
+        }
+    }
+
+    #[test]
+    fn test_bitsvec_of_char_holds_codepoints_above_u8_range() {
+        let symbols = ['h', 'é', '世', '🎉'];
+        let bits = 21; // enough for any Unicode scalar value
+        let mut vec = BitsVec::with_capacity(bits, symbols.len());
+        for &c in &symbols {
+            vec.push(c);
+        }
+
+        assert_eq!(vec.iter().collect::<Vec<char>>(), symbols);
+    }
+
     #[test]
     fn test_everything_with_enum() {
         let mut vec = BitsVec::with_elements(4, 16, TestEnum::Value4);
@@ -366,4 +938,266 @@ mod tests {
             assert_eq!(vec.get(i), TestEnum::Value4);
         }
     }
+
+    #[test]
+    fn test_rank_select_empty() {
+        let mut vec: BitsVec<bool> = BitsVec::new(1);
+        assert_eq!(vec.rank1(0), 0);
+        assert_eq!(vec.select1(0), None);
+    }
+
+    #[test]
+    fn test_rank_select_against_naive() {
+        let bits = [
+            true, false, true, true, false, false, true, false, true, true,
+            false, true, false, false, false, true, true, true, false, true,
+        ];
+
+        let mut vec = BitsVec::new(1);
+        for &bit in bits.iter() {
+            vec.push(bit);
+        }
+
+        for i in 0..=bits.len() {
+            let naive = bits[..i].iter().filter(|&&b| b).count();
+            assert_eq!(vec.rank1(i), naive, "rank1({}) mismatch", i);
+        }
+
+        let ones: Vec<usize> = bits.iter().enumerate().filter(|&(_, &b)| b).map(|(i, _)| i).collect();
+        for (j, &pos) in ones.iter().enumerate() {
+            assert_eq!(vec.select1(j), Some(pos), "select1({}) mismatch", j);
+        }
+        assert_eq!(vec.select1(ones.len()), None);
+    }
+
+    #[test]
+    fn test_rank_select_spans_superblocks_and_invalidates() {
+        // 3 superblocks' worth of words (24 > 2 * 8 words) so the directory has
+        // more than one entry, then mutate and make sure the cache is rebuilt.
+        let n = BitsVec::<bool>::SUPERBLOCK_WORDS * mem::size_of::<usize>() * 8 * 3;
+        let mut vec = BitsVec::with_elements(1, n, false);
+        for i in (0..n).step_by(3) {
+            vec.set(i, true);
+        }
+
+        let expected_total = (0..n).step_by(3).count();
+        assert_eq!(vec.rank1(n), expected_total);
+        assert_eq!(vec.select1(expected_total), None);
+
+        vec.set(1, true);
+        assert_eq!(vec.rank1(n), expected_total + 1);
+    }
+
+    #[test]
+    fn test_io_roundtrip_via_reader() {
+        let mut vec: BitsVec<u32> = BitsVec::new(11);
+        for i in 0..500 {
+            vec.push((i * 37) % 2000);
+        }
+
+        let mut buf = Vec::new();
+        vec.to_writer(&mut buf).unwrap();
+
+        let mut cursor = &buf[..];
+        let restored = BitsVec::<u32>::from_reader(&mut cursor).unwrap();
+        assert_eq!(restored, vec);
+        assert_eq!(cursor.len(), 0, "from_reader should consume exactly the written bytes");
+    }
+
+    #[test]
+    fn test_io_roundtrip_via_bytes() {
+        let mut vec: BitsVec<u32> = BitsVec::new(11);
+        for i in 0..500 {
+            vec.push((i * 37) % 2000);
+        }
+
+        let mut buf = Vec::new();
+        vec.to_writer(&mut buf).unwrap();
+
+        let restored = BitsVec::<u32>::from_bytes(&buf).unwrap();
+        assert_eq!(restored, vec);
+    }
+
+    #[test]
+    fn test_bitsvecref_from_bytes_matches_owned_without_copying_elements() {
+        use super::BitsVecRef;
+
+        let mut vec: BitsVec<u32> = BitsVec::new(11);
+        for i in 0..500 {
+            vec.push((i * 37) % 2000);
+        }
+
+        let mut buf = Vec::new();
+        vec.to_writer(&mut buf).unwrap();
+
+        let view = BitsVecRef::<u32>::from_bytes(&buf).unwrap();
+        assert_eq!(view.len(), vec.len());
+        for i in 0..vec.len() {
+            assert_eq!(view.get(i), vec.get(i));
+        }
+        assert_eq!(view.iter().collect::<Vec<_>>(), vec.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bitsvecref_from_bytes_rejects_truncated_buffer() {
+        use super::BitsVecRef;
+
+        let mut vec: BitsVec<u32> = BitsVec::new(11);
+        for i in 0..50 {
+            vec.push((i * 37) % 2000);
+        }
+
+        let mut buf = Vec::new();
+        vec.to_writer(&mut buf).unwrap();
+        buf.truncate(buf.len() - 1);
+
+        let result = BitsVecRef::<u32>::from_bytes(&buf);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_io_roundtrip_empty() {
+        let vec: BitsVec<u32> = BitsVec::new(5);
+        let mut buf = Vec::new();
+        vec.to_writer(&mut buf).unwrap();
+
+        let restored = BitsVec::<u32>::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(restored, vec);
+    }
+
+    #[test]
+    fn test_from_reader_rejects_bad_version() {
+        let buf = [0xff];
+        let result = BitsVec::<u32>::from_reader(&mut &buf[..]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_reader_rejects_corrupted_huge_length_gracefully() {
+        let mut buf = Vec::new();
+        write_u64(&mut buf, super::FORMAT_VERSION as u64).unwrap();
+        write_u64(&mut buf, 11).unwrap(); // bits
+        write_u64(&mut buf, 0).unwrap(); // units
+        write_u64(&mut buf, 64).unwrap(); // max_bits
+        write_u64(&mut buf, 0).unwrap(); // leftover
+        write_u64(&mut buf, u64::MAX).unwrap(); // inner_len: corrupted/huge
+        // No word data follows, so a correct reader must fail on the first
+        // read rather than trying to allocate for `u64::MAX` words.
+
+        let result = BitsVec::<u32>::from_reader(&mut &buf[..]);
+        assert!(result.is_err());
+    }
+
+    fn packed(values: &[u32]) -> BitsVec<u32> {
+        let mut vec = BitsVec::new(11);
+        for &value in values {
+            vec.push(value);
+        }
+        vec
+    }
+
+    #[test]
+    fn test_pop_matches_vec() {
+        let model: Vec<u32> = (0..40).map(|i| i * 37 % 2000).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        while let Some(expected) = model.pop() {
+            assert_eq!(vec.pop(), Some(expected));
+            assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+        }
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn test_truncate_matches_vec() {
+        let model: Vec<u32> = (0..40).map(|i| i * 37 % 2000).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        vec.truncate(50); // no-op, longer than the vector
+        assert_eq!(vec.len(), 40);
+
+        vec.truncate(17);
+        model.truncate(17);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+
+        vec.truncate(0);
+        model.truncate(0);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn test_remove_straddles_word_boundary() {
+        let model: Vec<u32> = (0..40).map(|i| i * 37 % 2000).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        for &i in &[0, 17, 20, 0, 35 - 3] {
+            assert_eq!(vec.remove(i), model.remove(i));
+            assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+        }
+    }
+
+    #[test]
+    fn test_insert_straddles_word_boundary() {
+        let model: Vec<u32> = (0..40).map(|i| i * 37 % 2000).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        for &(i, value) in &[(0, 111), (20, 222), (17, 444)] {
+            vec.insert(i, value);
+            model.insert(i, value);
+            assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+        }
+
+        let end = model.len();
+        vec.insert(end, 999);
+        model.insert(end, 999);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+    }
+
+    #[test]
+    fn test_insert_into_empty() {
+        let mut vec: BitsVec<u32> = BitsVec::new(11);
+        vec.insert(0, 42);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![42]);
+    }
+
+    #[test]
+    fn test_drain_collects_and_compacts() {
+        let model: Vec<u32> = (0..40).map(|i| i * 13 % 2000).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        let drained: Vec<u32> = vec.drain(10..25).collect();
+        let expected: Vec<u32> = model.drain(10..25).collect();
+        assert_eq!(drained, expected);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+    }
+
+    #[test]
+    fn test_drain_partial_consumption_still_compacts() {
+        let model: Vec<u32> = (0..20).collect();
+        let mut vec = packed(&model);
+        let mut model = model;
+
+        {
+            let mut drain = vec.drain(5..15);
+            assert_eq!(drain.next(), Some(5));
+            assert_eq!(drain.next(), Some(6));
+            // Dropping here without consuming the rest should still remove it.
+        }
+        model.drain(5..15);
+
+        assert_eq!(vec.iter().collect::<Vec<_>>(), model);
+    }
+
+    #[test]
+    fn test_drain_empty_range_on_empty_vec() {
+        let mut vec: BitsVec<u32> = BitsVec::new(5);
+        assert_eq!(vec.drain(0..0).collect::<Vec<_>>(), Vec::<u32>::new());
+        assert_eq!(vec.len(), 0);
+    }
 }
@@ -0,0 +1,322 @@
+//! Suffix array construction.
+
+use std::io::{self, Read, Write};
+
+use fillings::BitsVec;
+
+/// Builds the suffix array of `text` (the permutation of `0..text.len()` that
+/// sorts every suffix of `text` lexicographically) using a plain comparison sort.
+///
+/// `text` is a sequence of symbol codes rather than raw bytes so that callers
+/// (e.g. [`FMIndex`](crate::FMIndex)) can index alphabets wider than a byte.
+pub fn suffix_array(text: &[usize]) -> Vec<usize> {
+    let mut sa: Vec<usize> = (0..text.len()).collect();
+    sa.sort_by(|&a, &b| text[a..].cmp(&text[b..]));
+    sa
+}
+
+/// Writes a suffix array to `writer`, packing each entry into `BitsVec`'s
+/// `ceil(log2(n))`-bit-wide representation rather than 8 bytes per `usize`.
+pub fn write_suffix_array<W: Write>(sa: &[usize], writer: &mut W) -> io::Result<()> {
+    let bits = bits_for(sa.len());
+    let mut packed = BitsVec::with_capacity(bits, sa.len());
+    for &position in sa {
+        packed.push(position);
+    }
+
+    packed.to_writer(writer)
+}
+
+/// Reconstructs a suffix array previously written with `write_suffix_array`.
+pub fn read_suffix_array<R: Read>(reader: &mut R) -> io::Result<Vec<usize>> {
+    let packed: BitsVec<usize> = BitsVec::from_reader(reader)?;
+    Ok(packed.iter().collect())
+}
+
+fn bits_for(len: usize) -> usize {
+    let mut bits = 1;
+    while (1usize << bits) < len {
+        bits += 1;
+    }
+    bits
+}
+
+/// Builds the suffix array of `text` in O(n) via SA-IS (induced sorting),
+/// packing the result into a `BitsVec` sized to `ceil(log2(text.len() + 1))`
+/// bits rather than a full `Vec<usize>`. `sais` packs its working arrays the
+/// same way at every level of its recursion: the S/L type bitmap is a
+/// `BitsVec<bool>` (1 bit per entry), and the working suffix array, bucket
+/// sizes and LMS names are `BitsVec<usize>` sized to `ceil(log2(n + 1))`
+/// bits, using `n` itself (one past the last valid index/name) as the
+/// reserved in-range sentinel for "empty slot", in place of `usize::MAX`.
+///
+/// `text` must not contain the symbol whose code is `0`; that code is
+/// reserved as the unique sentinel appended internally to terminate every
+/// suffix, the same convention `bwt` uses.
+pub fn suffix_array_sais(text: &[usize]) -> BitsVec<usize> {
+    let mut codes: Vec<usize> = text.to_vec();
+    codes.push(0);
+
+    let alphabet_size = codes.iter().cloned().max().map_or(1, |max| max + 1);
+    let sa = sais(&codes, alphabet_size);
+
+    let bits = bits_for(sa.len());
+    let mut packed = BitsVec::with_capacity(bits, sa.len());
+    for position in sa {
+        packed.push(position);
+    }
+    packed
+}
+
+/// Returns whether position `i` is a left-most-S (LMS) suffix: an S-type
+/// position immediately preceded by an L-type one. `t.get(i)` is `true` for
+/// S-type (the suffix starting at `i` is lexicographically smaller than the
+/// one starting at `i + 1`, or equal to it and the rest agrees).
+fn is_lms(t: &BitsVec<bool>, i: usize) -> bool {
+    i > 0 && t.get(i) && !t.get(i - 1)
+}
+
+fn bucket_starts(sizes: &BitsVec<usize>) -> Vec<usize> {
+    let mut sum = 0;
+    sizes.iter().map(|size| { let start = sum; sum += size; start }).collect()
+}
+
+fn bucket_ends(sizes: &BitsVec<usize>) -> Vec<usize> {
+    let mut sum = 0;
+    sizes.iter().map(|size| { sum += size; sum }).collect()
+}
+
+/// Scans `sa` left to right, placing each not-yet-sorted L-type suffix right
+/// after the suffix that induced it, at the front of its symbol's bucket.
+/// `s.len()` doubles as the "empty slot" sentinel `sa` was built with.
+fn induce_l(sa: &mut BitsVec<usize>, s: &[usize], t: &BitsVec<bool>, bucket_sizes: &BitsVec<usize>) {
+    let empty = s.len();
+    let mut starts = bucket_starts(bucket_sizes);
+    for i in 0..sa.len() {
+        let j = sa.get(i);
+        if j == empty || j == 0 {
+            continue
+        }
+
+        let prev = j - 1;
+        if !t.get(prev) {
+            let c = s[prev];
+            sa.set(starts[c], prev);
+            starts[c] += 1;
+        }
+    }
+}
+
+/// The S-type counterpart of `induce_l`: scans `sa` right to left, placing
+/// each S-type suffix at the back of its symbol's bucket.
+fn induce_s(sa: &mut BitsVec<usize>, s: &[usize], t: &BitsVec<bool>, bucket_sizes: &BitsVec<usize>) {
+    let empty = s.len();
+    let mut ends = bucket_ends(bucket_sizes);
+    for i in (0..sa.len()).rev() {
+        let j = sa.get(i);
+        if j == empty || j == 0 {
+            continue
+        }
+
+        let prev = j - 1;
+        if t.get(prev) {
+            let c = s[prev];
+            ends[c] -= 1;
+            sa.set(ends[c], prev);
+        }
+    }
+}
+
+/// Compares the LMS substrings starting at `a` and `b` (each running up to
+/// and including the *next* LMS position), the equality test SA-IS uses to
+/// assign names to LMS substrings before recursing on the reduced problem.
+fn lms_substrings_equal(s: &[usize], t: &BitsVec<bool>, a: usize, b: usize) -> bool {
+    if a == b {
+        return true
+    }
+
+    let n = s.len();
+    let mut i = 0;
+    loop {
+        let (ai, bi) = (a + i, b + i);
+        let (a_end, b_end) = (ai >= n, bi >= n);
+        if a_end || b_end {
+            return a_end == b_end
+        }
+        if s[ai] != s[bi] || t.get(ai) != t.get(bi) {
+            return false
+        }
+        if i > 0 && (is_lms(t, ai) || is_lms(t, bi)) {
+            return is_lms(t, ai) && is_lms(t, bi)
+        }
+
+        i += 1;
+    }
+}
+
+/// The SA-IS suffix array construction. `s` must end with a unique symbol
+/// smaller than every other symbol in `s`. Runs in O(`s.len()` + `alphabet_size`).
+fn sais(s: &[usize], alphabet_size: usize) -> Vec<usize> {
+    let n = s.len();
+    if n <= 1 {
+        return (0..n).collect()
+    }
+
+    let mut t = BitsVec::with_elements(1, n, false);
+    t.set(n - 1, true);
+    for i in (0..n - 1).rev() {
+        let value = if s[i] < s[i + 1] {
+            true
+        } else if s[i] > s[i + 1] {
+            false
+        } else {
+            t.get(i + 1)
+        };
+        t.set(i, value);
+    }
+
+    // `n` itself never occurs as a valid position, bucket count or LMS name
+    // below (all of those stay in `0..n`), so it doubles as the "empty slot"
+    // sentinel instead of `usize::MAX`, letting these arrays pack into
+    // `ceil(log2(n + 1))` bits instead of a full `usize` each.
+    let packed_bits = bits_for(n + 1);
+
+    let mut bucket_sizes: BitsVec<usize> = BitsVec::with_elements(packed_bits, alphabet_size, 0);
+    for &c in s {
+        let count = bucket_sizes.get(c);
+        bucket_sizes.set(c, count + 1);
+    }
+
+    let mut sa: BitsVec<usize> = BitsVec::with_elements(packed_bits, n, n);
+    {
+        let mut ends = bucket_ends(&bucket_sizes);
+        for i in (0..n).rev() {
+            if is_lms(&t, i) {
+                let c = s[i];
+                ends[c] -= 1;
+                sa.set(ends[c], i);
+            }
+        }
+    }
+
+    induce_l(&mut sa, s, &t, &bucket_sizes);
+    induce_s(&mut sa, s, &t, &bucket_sizes);
+
+    let lms_positions: Vec<usize> = (0..n).filter(|&i| is_lms(&t, i)).collect();
+    let sorted_lms: Vec<usize> = sa.iter().filter(|&i| is_lms(&t, i)).collect();
+
+    let mut names: BitsVec<usize> = BitsVec::with_elements(packed_bits, n, n);
+    let mut name = 0;
+    names.set(sorted_lms[0], name);
+    for window in sorted_lms.windows(2) {
+        if !lms_substrings_equal(s, &t, window[0], window[1]) {
+            name += 1;
+        }
+        names.set(window[1], name);
+    }
+    let num_names = name + 1;
+
+    let reduced: Vec<usize> = lms_positions.iter().map(|&i| names.get(i)).collect();
+    let reduced_sa = if num_names < lms_positions.len() {
+        sais(&reduced, num_names)
+    } else {
+        // Every LMS substring is already unique, so its name directly gives
+        // its rank among the other LMS suffixes; no need to recurse.
+        let mut order = vec![0; lms_positions.len()];
+        for (rank, &code) in reduced.iter().enumerate() {
+            order[code] = rank;
+        }
+        order
+    };
+
+    let sorted_lms: Vec<usize> = reduced_sa.iter().map(|&i| lms_positions[i]).collect();
+
+    let mut sa: BitsVec<usize> = BitsVec::with_elements(packed_bits, n, n);
+    {
+        let mut ends = bucket_ends(&bucket_sizes);
+        for &i in sorted_lms.iter().rev() {
+            let c = s[i];
+            ends[c] -= 1;
+            sa.set(ends[c], i);
+        }
+    }
+
+    induce_l(&mut sa, s, &t, &bucket_sizes);
+    induce_s(&mut sa, s, &t, &bucket_sizes);
+
+    sa.iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_suffix_array, suffix_array, suffix_array_sais, write_suffix_array};
+
+    fn codes(s: &str) -> Vec<usize> {
+        s.bytes().map(|b| b as usize).collect()
+    }
+
+    // Shift every byte up by one so that code `0` stays free for the sentinel
+    // `suffix_array_sais` appends, matching `bwt`'s convention.
+    fn sentinel_free_codes(s: &str) -> Vec<usize> {
+        s.bytes().map(|b| b as usize + 1).collect()
+    }
+
+    fn naive_sa_with_sentinel(codes: &[usize]) -> Vec<usize> {
+        let mut with_sentinel = codes.to_vec();
+        with_sentinel.push(0);
+        suffix_array(&with_sentinel)
+    }
+
+    #[test]
+    fn test_suffix_array_banana() {
+        assert_eq!(suffix_array(&codes("banana$")), vec![6, 5, 3, 1, 0, 4, 2]);
+    }
+
+    #[test]
+    fn test_suffix_array_empty() {
+        assert_eq!(suffix_array(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_suffix_array_io_roundtrip() {
+        for text in &["banana$", "mississippi$", "$"] {
+            let sa = suffix_array(&codes(text));
+            let mut buf = Vec::new();
+            write_suffix_array(&sa, &mut buf).unwrap();
+            assert_eq!(read_suffix_array(&mut &buf[..]).unwrap(), sa);
+        }
+    }
+
+    #[test]
+    fn test_suffix_array_io_roundtrip_empty() {
+        let sa = suffix_array(&[]);
+        let mut buf = Vec::new();
+        write_suffix_array(&sa, &mut buf).unwrap();
+        assert_eq!(read_suffix_array(&mut &buf[..]).unwrap(), sa);
+    }
+
+    #[test]
+    fn test_sais_matches_naive() {
+        for text in &["banana", "abracadabra", "mississippi", "aaaaaaaa", "helixrustwavelet"] {
+            let codes = sentinel_free_codes(text);
+            let expected = naive_sa_with_sentinel(&codes);
+            let actual: Vec<usize> = suffix_array_sais(&codes).iter().collect();
+            assert_eq!(actual, expected, "text {:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_sais_single_char() {
+        let codes = sentinel_free_codes("a");
+        let expected = naive_sa_with_sentinel(&codes);
+        let actual: Vec<usize> = suffix_array_sais(&codes).iter().collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_sais_empty() {
+        let expected = naive_sa_with_sentinel(&[]);
+        let actual: Vec<usize> = suffix_array_sais(&[]).iter().collect();
+        assert_eq!(actual, expected);
+    }
+}
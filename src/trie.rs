@@ -0,0 +1,215 @@
+//! A simple prefix tree over sequences of a `ReprUsize` alphabet.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use fillings::ReprUsize;
+
+use io_util::{invalid_data, read_u64, write_u64, MAX_PREALLOC};
+
+// Bumped whenever the on-disk layout written by `Trie::to_writer` changes.
+const FORMAT_VERSION: u8 = 1;
+
+struct Node {
+    children: HashMap<usize, Node>,
+    terminal: bool,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node { children: HashMap::new(), terminal: false }
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[self.terminal as u8])?;
+        write_u64(writer, self.children.len() as u64)?;
+        for (&code, child) in &self.children {
+            write_u64(writer, code as u64)?;
+            child.to_writer(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn from_reader<R: Read>(reader: &mut R) -> io::Result<Node> {
+        let mut terminal = [0; 1];
+        reader.read_exact(&mut terminal)?;
+        let child_count = read_u64(reader)? as usize;
+
+        let mut children = HashMap::with_capacity(cmp::min(child_count, MAX_PREALLOC));
+        for _ in 0..child_count {
+            let code = read_u64(reader)? as usize;
+            children.insert(code, Node::from_reader(reader)?);
+        }
+
+        Ok(Node { children: children, terminal: terminal[0] != 0 })
+    }
+}
+
+pub struct Trie<T: ReprUsize> {
+    root: Node,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ReprUsize + Clone> Trie<T> {
+    pub fn new() -> Trie<T> {
+        Trie { root: Node::new(), len: 0, _marker: PhantomData }
+    }
+
+    pub fn insert(&mut self, sequence: &[T]) {
+        let mut node = &mut self.root;
+        for symbol in sequence {
+            node = node.children.entry(symbol.clone().into_usize()).or_insert_with(Node::new);
+        }
+
+        if !node.terminal {
+            node.terminal = true;
+            self.len += 1;
+        }
+    }
+
+    pub fn contains(&self, sequence: &[T]) -> bool {
+        self.find(sequence).map_or(false, |node| node.terminal)
+    }
+
+    pub fn contains_prefix(&self, sequence: &[T]) -> bool {
+        self.find(sequence).is_some()
+    }
+
+    fn find(&self, sequence: &[T]) -> Option<&Node> {
+        let mut node = &self.root;
+        for symbol in sequence {
+            node = node.children.get(&symbol.clone().into_usize())?;
+        }
+        Some(node)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes this trie to `writer`: a header of the format version, followed
+    /// by a pre-order walk of `(terminal, child count, [code, subtree]*)` records.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_u64(writer, FORMAT_VERSION as u64)?;
+        self.root.to_writer(writer)
+    }
+
+    /// Reconstructs a `Trie` previously written with `to_writer`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<Trie<T>> {
+        let version = read_u64(reader)?;
+        if version != FORMAT_VERSION as u64 {
+            return Err(invalid_data(format!("unsupported Trie format version {}", version)))
+        }
+
+        let root = Node::from_reader(reader)?;
+        let len = count_terminals(&root);
+        Ok(Trie { root: root, len: len, _marker: PhantomData })
+    }
+}
+
+fn count_terminals(node: &Node) -> usize {
+    node.children.values().map(count_terminals).sum::<usize>() + node.terminal as usize
+}
+
+impl<T: ReprUsize + Clone> Default for Trie<T> {
+    fn default() -> Trie<T> {
+        Trie::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::Trie;
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut trie: Trie<u8> = Trie::new();
+        trie.insert(b"banana");
+        trie.insert(b"band");
+
+        assert!(trie.contains(b"banana"));
+        assert!(trie.contains(b"band"));
+        assert!(!trie.contains(b"ban"));
+        assert!(trie.contains_prefix(b"ban"));
+        assert!(!trie.contains_prefix(b"bx"));
+        assert_eq!(trie.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_is_idempotent() {
+        let mut trie: Trie<u8> = Trie::new();
+        trie.insert(b"a");
+        trie.insert(b"a");
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_sequence_is_root() {
+        let mut trie: Trie<u8> = Trie::new();
+        assert!(!trie.contains(b""));
+        trie.insert(b"");
+        assert!(trie.contains(b""));
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_io_roundtrip() {
+        let mut trie: Trie<u8> = Trie::new();
+        trie.insert(b"banana");
+        trie.insert(b"band");
+        trie.insert(b"can");
+
+        let mut buf = Vec::new();
+        trie.to_writer(&mut buf).unwrap();
+
+        let restored: Trie<u8> = Trie::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(restored.len(), trie.len());
+        assert!(restored.contains(b"banana"));
+        assert!(restored.contains(b"band"));
+        assert!(restored.contains(b"can"));
+        assert!(!restored.contains(b"ban"));
+        assert!(restored.contains_prefix(b"ban"));
+    }
+
+    #[test]
+    fn test_io_roundtrip_empty() {
+        let trie: Trie<u8> = Trie::new();
+        let mut buf = Vec::new();
+        trie.to_writer(&mut buf).unwrap();
+
+        let restored: Trie<u8> = Trie::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(restored.len(), 0);
+    }
+
+    #[test]
+    fn test_from_reader_rejects_bad_version() {
+        let buf = [0xff];
+        let result: io::Result<Trie<u8>> = Trie::from_reader(&mut &buf[..]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_reader_rejects_corrupted_huge_length_gracefully() {
+        use io_util::write_u64;
+
+        let mut buf = Vec::new();
+        write_u64(&mut buf, super::FORMAT_VERSION as u64).unwrap();
+        buf.push(0); // terminal: false
+        write_u64(&mut buf, u64::MAX).unwrap(); // child_count: corrupted/huge
+        // No child data follows, so a correct reader must fail on the first
+        // read rather than trying to allocate for `u64::MAX` children.
+
+        let result: io::Result<Trie<u8>> = Trie::from_reader(&mut &buf[..]);
+        assert!(result.is_err());
+    }
+}
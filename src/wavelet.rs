@@ -0,0 +1,302 @@
+//! A wavelet tree over a small `usize` alphabet `[0, sigma)`, used by
+//! [`FMIndex`](crate::FMIndex) to support `access`/`rank`/`select` on
+//! arbitrary symbol codes instead of just bytes.
+//!
+//! Each node is a 1-bit [`BitsVec`] keyed on a single bit of the alphabet:
+//! the root splits the sequence on its most significant alphabet bit, and
+//! each half recurses on the next bit down, so the tree has `ceil(log2(sigma))`
+//! levels and every element touches exactly one node per level.
+
+use std::io::{self, Read, Write};
+
+use fillings::BitsVec;
+
+use io_util::{read_u64, write_u64};
+
+struct Node {
+    bits: BitsVec<bool>,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+impl Node {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.bits.to_writer(writer)?;
+        write_child(writer, self.left.as_deref())?;
+        write_child(writer, self.right.as_deref())
+    }
+
+    fn from_reader<R: Read>(reader: &mut R) -> io::Result<Node> {
+        let bits = BitsVec::from_reader(reader)?;
+        let left = read_child(reader)?;
+        let right = read_child(reader)?;
+        Ok(Node { bits: bits, left: left, right: right })
+    }
+}
+
+fn write_child<W: Write>(writer: &mut W, child: Option<&Node>) -> io::Result<()> {
+    match child {
+        Some(node) => {
+            writer.write_all(&[1])?;
+            node.to_writer(writer)
+        }
+        None => writer.write_all(&[0]),
+    }
+}
+
+fn read_child<R: Read>(reader: &mut R) -> io::Result<Option<Box<Node>>> {
+    let mut present = [0; 1];
+    reader.read_exact(&mut present)?;
+    if present[0] == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(Box::new(Node::from_reader(reader)?)))
+    }
+}
+
+impl Node {
+    fn build(symbols: &[usize], bit: usize) -> Node {
+        let mut bits = BitsVec::with_capacity(1, symbols.len());
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+
+        for &symbol in symbols {
+            let goes_right = (symbol >> bit) & 1 == 1;
+            bits.push(goes_right);
+            if goes_right {
+                right.push(symbol);
+            } else {
+                left.push(symbol);
+            }
+        }
+
+        let (left, right) = if bit == 0 {
+            (None, None)
+        } else {
+            let left = if left.is_empty() { None } else { Some(Box::new(Node::build(&left, bit - 1))) };
+            let right = if right.is_empty() { None } else { Some(Box::new(Node::build(&right, bit - 1))) };
+            (left, right)
+        };
+
+        Node { bits: bits, left: left, right: right }
+    }
+
+    fn select(&mut self, level: usize, symbol: usize, j: usize) -> Option<usize> {
+        let goes_right = (symbol >> level) & 1 == 1;
+        let local = if level == 0 {
+            j
+        } else {
+            let child = if goes_right { self.right.as_deref_mut() } else { self.left.as_deref_mut() };
+            child?.select(level - 1, symbol, j)?
+        };
+        select_bit(&mut self.bits, goes_right, local)
+    }
+}
+
+/// Finds the position of the `j`-th bit equal to `target` in `bits`.
+fn select_bit(bits: &mut BitsVec<bool>, target: bool, j: usize) -> Option<usize> {
+    if target {
+        return bits.select1(j)
+    }
+
+    // `BitsVec` only offers `select1`, so find the `j`-th zero by binary
+    // searching on `rank0(i) = i - rank1(i)`, which is monotonic in `i`.
+    let len = bits.len();
+    if j >= len - bits.rank1(len) {
+        return None
+    }
+
+    let mut lo = 0;
+    let mut hi = len;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if mid - bits.rank1(mid) <= j {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Some(lo - 1)
+}
+
+/// A balanced binary wavelet tree over symbol codes in `[0, alphabet_size)`.
+///
+/// This is an internal building block for [`FMIndex`](crate::FMIndex); it is
+/// not part of the crate's public surface.
+pub(crate) struct WaveletTree {
+    root: Option<Node>,
+    len: usize,
+    bit_depth: usize,
+}
+
+impl WaveletTree {
+    /// Builds a wavelet tree over `symbols`, each of which must be `< alphabet_size`.
+    pub(crate) fn new(symbols: &[usize], alphabet_size: usize) -> WaveletTree {
+        let bit_depth = bits_for(alphabet_size);
+        let root = if bit_depth == 0 || symbols.is_empty() {
+            None
+        } else {
+            Some(Node::build(symbols, bit_depth - 1))
+        };
+
+        WaveletTree { root: root, len: symbols.len(), bit_depth: bit_depth }
+    }
+
+    /// Returns the symbol at position `i`. O(log sigma).
+    pub(crate) fn access(&mut self, i: usize) -> usize {
+        assert!(i < self.len, "[access] index out of bounds ({} >= {})", i, self.len);
+        let mut symbol = 0;
+        let mut pos = i;
+        let mut node = self.root.as_mut();
+
+        while let Some(n) = node {
+            let bit = n.bits.get(pos);
+            symbol = (symbol << 1) | bit as usize;
+            pos = if bit { n.bits.rank1(pos) } else { pos - n.bits.rank1(pos) };
+            node = if bit { n.right.as_deref_mut() } else { n.left.as_deref_mut() };
+        }
+
+        symbol
+    }
+
+    /// Counts occurrences of `symbol` in `[0, i)`. O(log sigma).
+    pub(crate) fn rank(&mut self, i: usize, symbol: usize) -> usize {
+        assert!(i <= self.len, "[rank] index out of bounds ({} > {})", i, self.len);
+        if symbol >> self.bit_depth != 0 {
+            return 0    // wider than the tree's alphabet, so it never occurs
+        }
+
+        let mut pos = i;
+        let mut node = self.root.as_mut();
+        let mut level = self.bit_depth;
+
+        while level > 0 {
+            level -= 1;
+            let n = match node {
+                Some(n) => n,
+                None => return 0,
+            };
+
+            let goes_right = (symbol >> level) & 1 == 1;
+            pos = if goes_right { n.bits.rank1(pos) } else { pos - n.bits.rank1(pos) };
+            node = if goes_right { n.right.as_deref_mut() } else { n.left.as_deref_mut() };
+        }
+
+        pos
+    }
+
+    /// Finds the position of the `j`-th occurrence of `symbol` (0-indexed).
+    /// O(log sigma * log n): each level runs a bit-level select over its node.
+    pub(crate) fn select(&mut self, symbol: usize, j: usize) -> Option<usize> {
+        if symbol >> self.bit_depth != 0 {
+            return None     // wider than the tree's alphabet, so it never occurs
+        }
+
+        if self.bit_depth == 0 {
+            return if symbol == 0 && j < self.len { Some(j) } else { None }
+        }
+
+        self.root.as_mut()?.select(self.bit_depth - 1, symbol, j)
+    }
+
+    pub(crate) fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_u64(writer, self.len as u64)?;
+        write_u64(writer, self.bit_depth as u64)?;
+        write_child(writer, self.root.as_ref())
+    }
+
+    pub(crate) fn from_reader<R: Read>(reader: &mut R) -> io::Result<WaveletTree> {
+        let len = read_u64(reader)? as usize;
+        let bit_depth = read_u64(reader)? as usize;
+        let root = read_child(reader)?.map(|node| *node);
+        Ok(WaveletTree { root: root, len: len, bit_depth: bit_depth })
+    }
+}
+
+fn bits_for(alphabet_size: usize) -> usize {
+    let mut bits = 0;
+    while (1usize << bits) < alphabet_size {
+        bits += 1;
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WaveletTree;
+
+    fn symbols() -> Vec<usize> {
+        // "mississippi" mapped to a small alphabet: i=0, m=1, p=2, s=3
+        vec![1, 0, 3, 3, 0, 3, 3, 0, 2, 2, 0]
+    }
+
+    #[test]
+    fn test_access_matches_input() {
+        let input = symbols();
+        let mut tree = WaveletTree::new(&input, 4);
+        for (i, &symbol) in input.iter().enumerate() {
+            assert_eq!(tree.access(i), symbol);
+        }
+    }
+
+    #[test]
+    fn test_rank_matches_naive() {
+        let input = symbols();
+        let mut tree = WaveletTree::new(&input, 4);
+        for symbol in 0..4 {
+            for i in 0..=input.len() {
+                let naive = input[..i].iter().filter(|&&s| s == symbol).count();
+                assert_eq!(tree.rank(i, symbol), naive, "rank({}, {}) mismatch", i, symbol);
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_matches_naive() {
+        let input = symbols();
+        let mut tree = WaveletTree::new(&input, 4);
+        for symbol in 0..4 {
+            let positions: Vec<usize> = input.iter().enumerate()
+                .filter(|&(_, &s)| s == symbol)
+                .map(|(i, _)| i)
+                .collect();
+
+            for (j, &pos) in positions.iter().enumerate() {
+                assert_eq!(tree.select(symbol, j), Some(pos));
+            }
+            assert_eq!(tree.select(symbol, positions.len()), None);
+        }
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree = WaveletTree::new(&[], 4);
+        assert_eq!(tree.select(0, 0), None);
+    }
+
+    #[test]
+    fn test_io_roundtrip() {
+        let input = symbols();
+        let tree = WaveletTree::new(&input, 4);
+
+        let mut buf = Vec::new();
+        tree.to_writer(&mut buf).unwrap();
+
+        let mut restored = WaveletTree::from_reader(&mut &buf[..]).unwrap();
+        for (i, &symbol) in input.iter().enumerate() {
+            assert_eq!(restored.access(i), symbol);
+        }
+    }
+
+    #[test]
+    fn test_io_roundtrip_empty() {
+        let tree = WaveletTree::new(&[], 4);
+
+        let mut buf = Vec::new();
+        tree.to_writer(&mut buf).unwrap();
+
+        let mut restored = WaveletTree::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(restored.select(0, 0), None);
+    }
+}